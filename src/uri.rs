@@ -1,4 +1,7 @@
 //! HTTP RequestUris
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
 use url;
 use url::Url;
 
@@ -38,7 +41,7 @@ pub enum RequestUri {
     /// The authority form is only for use with `CONNECT` requests.
     ///
     /// An example StartLine: `CONNECT www.example.com:80 HTTP/1.1`.
-    Authority(String),
+    Authority(Authority),
 
     /// The star is used to target the entire server, instead of a specific resource.
     ///
@@ -46,10 +49,240 @@ pub enum RequestUri {
     Star,
 }
 
+/// A cheap hint, obtained without allocating or validating well-formedness,
+/// as to which of the four request-target forms a string represents. Used
+/// by callers such as routers to pick the right full parse path up front.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TargetForm {
+    /// A leading `/`, e.g. `/where?q=now`.
+    Origin,
+    /// A `://` anywhere, or a leading `scheme:` prefix, e.g.
+    /// `http://www.example.org/pub` or `mailto:foo@bar.com`.
+    Absolute,
+    /// A bare host[:port], e.g. `www.example.com:80`.
+    Authority,
+    /// The literal `*`.
+    Asterisk,
+}
+
+impl RequestUri {
+    /// Classify a request-target string by inspecting its leading bytes and
+    /// searching it for `://`, without allocating or validating
+    /// well-formedness. This is a
+    /// hint only: a result of `TargetForm::Authority`, for instance, does
+    /// not guarantee `input` is a valid authority.
+    pub fn classify(input: &str) -> TargetForm {
+        if input == "*" {
+            TargetForm::Asterisk
+        } else if input.starts_with('/') {
+            TargetForm::Origin
+        } else if input.contains("://") || has_scheme_prefix(input) {
+            TargetForm::Absolute
+        } else {
+            TargetForm::Authority
+        }
+    }
+}
+
+/// Whether `input` begins with a valid URI `scheme:` prefix whose remainder
+/// isn't simply a bare port number (which would make it look like a
+/// `host:port` authority instead, e.g. `example.com:8080`).
+///
+/// Real URI schemes (`mailto`, `tel`, `urn`, ...) never contain a `.`, while
+/// dotted names before a `:` are virtually always multi-label hostnames
+/// (`example.com:80`, `a.b.c:notaport`), so a `.` before the colon rules out
+/// a scheme entirely rather than risking an authority-form target being
+/// misread as a bogus-scheme absolute-form one.
+fn has_scheme_prefix(input: &str) -> bool {
+    let bytes = input.as_bytes();
+    if bytes.is_empty() || !bytes[0].is_ascii_alphabetic() {
+        return false;
+    }
+    let mut i = 1;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b':' {
+            let rest = &bytes[i + 1..];
+            return rest.is_empty() || !rest.iter().all(|b| b.is_ascii_digit());
+        }
+        if b == b'.' {
+            return false;
+        }
+        if !(b.is_ascii_alphanumeric() || b == b'+' || b == b'-') {
+            return false;
+        }
+        i += 1;
+    }
+    false
+}
+
+impl fmt::Display for RequestUri {
+    /// Reconstruct the exact request-target text this `RequestUri` was (or
+    /// could have been) parsed from, so it can be re-emitted in a start line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RequestUri::AbsolutePath(ref pqf) => f.write_str(&pqf.to_encoded_string()),
+            RequestUri::AbsoluteUri(ref url) => write!(f, "{}", url),
+            RequestUri::Authority(ref authority) => write!(f, "{}", authority),
+            RequestUri::Star => f.write_str("*"),
+        }
+    }
+}
+
+impl FromStr for RequestUri {
+    type Err = UriError;
+
+    /// Parse a request-target string into the matching `RequestUri` form.
+    fn from_str(s: &str) -> Result<RequestUri, UriError> {
+        if s == "*" {
+            Ok(RequestUri::Star)
+        } else if s.starts_with('/') {
+            Ok(RequestUri::AbsolutePath(PathQueryFragment::from_encoded(s)))
+        } else if s.contains("://") || has_scheme_prefix(s) {
+            Url::parse(s).map(RequestUri::AbsoluteUri).map_err(|_| UriError::InvalidUrl)
+        } else {
+            Authority::parse(s).map(RequestUri::Authority)
+        }
+    }
+}
+
+/// An error encountered while parsing a piece of a `RequestUri`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum UriError {
+    /// The authority-form target had no host.
+    EmptyHost,
+    /// The authority-form target's host was not a well-formed IPv6 literal:
+    /// a bracketed body that is not a valid IPv6 address, anything other
+    /// than `:port` after the closing `]`, or an unbracketed host containing `:`.
+    InvalidIpv6,
+    /// The authority-form target's port was not a valid, in-range port number.
+    InvalidPort,
+    /// An absolute-form target was not a well-formed URL.
+    InvalidUrl,
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UriError::EmptyHost => write!(f, "authority has an empty host"),
+            UriError::InvalidIpv6 => write!(f, "authority has a malformed IPv6 address"),
+            UriError::InvalidPort => write!(f, "authority has an invalid port"),
+            UriError::InvalidUrl => write!(f, "absolute-form target is not a well-formed URL"),
+        }
+    }
+}
+
+/// The authority component of a `CONNECT` request-target, e.g.
+/// `www.example.com:80` or `[2001:db8::1]:8080`, parsed per RFC 3986 §3.2.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Authority {
+    userinfo: Option<String>,
+    host: String,
+    port: Option<u16>,
+}
+
+impl Authority {
+    /// Parse an authority-form request-target into its userinfo, host and port.
+    pub fn parse(input: &str) -> Result<Authority, UriError> {
+        let (userinfo, rest) = match input.find('@') {
+            Some(idx) => (Some(input[..idx].to_owned()), &input[idx + 1..]),
+            None => (None, input),
+        };
+
+        // Split host from port on the last ':' that is outside of a
+        // bracketed IPv6 literal.
+        let (host, port) = if rest.starts_with('[') {
+            let close = rest.find(']').ok_or(UriError::InvalidIpv6)?;
+            let ipv6_body = &rest[1..close];
+            if ipv6_body.is_empty() || !is_valid_ipv6(ipv6_body) {
+                return Err(UriError::InvalidIpv6);
+            }
+            let host = rest[..close + 1].to_owned();
+            let remainder = &rest[close + 1..];
+            let port = if remainder.is_empty() {
+                None
+            } else if let Some(port_str) = remainder.strip_prefix(':') {
+                Some(parse_port(port_str)?)
+            } else {
+                return Err(UriError::InvalidIpv6);
+            };
+            (host, port)
+        } else {
+            match rest.rfind(':') {
+                Some(idx) => {
+                    let host_part = &rest[..idx];
+                    // An unbracketed host with more than one ':' is not a
+                    // valid authority (IPv6 literals must be bracketed),
+                    // rather than a host that happens to contain colons.
+                    if host_part.contains(':') {
+                        return Err(UriError::InvalidIpv6);
+                    }
+                    (host_part.to_owned(), Some(parse_port(&rest[idx + 1..])?))
+                }
+                None => (rest.to_owned(), None),
+            }
+        };
+
+        if host.is_empty() {
+            return Err(UriError::EmptyHost);
+        }
+
+        Ok(Authority { userinfo: userinfo, host: host, port: port })
+    }
+
+    /// The optional userinfo (e.g. `user:pass`), without the trailing `@`.
+    #[inline]
+    pub fn userinfo(&self) -> Option<&str> {
+        self.userinfo.as_ref().map(|s| s.as_str())
+    }
+
+    /// The host, including the surrounding brackets for an IPv6 literal.
+    #[inline]
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The optional port.
+    #[inline]
+    pub fn port(&self) -> Option<u16> {
+        self.port
+    }
+}
+
+impl fmt::Display for Authority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref userinfo) = self.userinfo {
+            write!(f, "{}@", userinfo)?;
+        }
+        f.write_str(&self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_port(input: &str) -> Result<u16, UriError> {
+    if input.is_empty() || !input.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(UriError::InvalidPort);
+    }
+    input.parse::<u16>().map_err(|_| UriError::InvalidPort)
+}
+
+fn is_valid_ipv6(body: &str) -> bool {
+    use std::net::Ipv6Addr;
+    body.parse::<Ipv6Addr>().is_ok()
+}
+
 #[derive(Debug, PartialEq, Clone)]
 /// An absolute URL path as seen by a server, such as /where?q=now
 pub struct PathQueryFragment {
-    /// The path component, as a String
+    /// The path component, as a sequence of segments. Each segment is kept
+    /// in its wire (percent-encoded) form, so a literal `/` can only ever
+    /// appear as a segment separator and an encoded `%2F` is preserved as
+    /// three characters inside a segment rather than being mistaken for
+    /// one. Use `decoded_segments()` to get the logical (percent-decoded)
+    /// values for matching.
     pub path: Vec<String>,
     /// The query component, optional, as a String.  Use query_pairs() method if it is
     /// application/x-www-form-urlencoded to break it down into a vector of (key,value)
@@ -57,7 +290,11 @@ pub struct PathQueryFragment {
     pub query: Option<String>,
     /// The HTTP RFC does not identify a fragment here, but it is generally parsed
     // in practice and discarded in case it exists
-    pub fragment: Option<String>
+    pub fragment: Option<String>,
+    /// Whether this path is rooted (began with a '/') as opposed to being a
+    /// relative-reference (e.g. as found in a `Location` header, or as the
+    /// second argument to `join()`).
+    pub absolute: bool,
 }
 
 impl PathQueryFragment {
@@ -67,4 +304,473 @@ impl PathQueryFragment {
     pub fn query_pairs(&self) -> Option<Vec<(String, String)>> {
         self.query.as_ref().map(|query| url::form_urlencoded::parse(query.as_bytes()))
     }
+
+    /// Append a single segment to the end of the path. `segment` is taken
+    /// as a literal (decoded) value; any `%`, `?`, `#`, `/`, `[`, `]` or `@`
+    /// in it is percent-encoded before it is stored, so a literal `%` or `/`
+    /// within it cannot later be mistaken for an escape sequence or a
+    /// separator. All other characters (spaces, non-ASCII) are stored as given.
+    pub fn push_segment(&mut self, segment: &str) -> &mut PathQueryFragment {
+        self.path.push(percent_encode_segment(segment));
+        self
+    }
+
+    /// Resolve `other` as a reference relative to `self` (per RFC 3986 §5.3's
+    /// merge algorithm) and normalize the result. `self` must be absolute.
+    pub fn join(&self, other: &PathQueryFragment) -> PathQueryFragment {
+        let mut joined = if other.absolute {
+            other.clone()
+        } else if other.path.is_empty() || (other.path.len() == 1 && other.path[0].is_empty()) {
+            // A reference with no path component (e.g. a query-only
+            // reference like `?x=y`, which `from_encoded()` gives a single
+            // empty segment) leaves the base path untouched.
+            PathQueryFragment {
+                path: self.path.clone(),
+                query: other.query.clone(),
+                fragment: other.fragment.clone(),
+                absolute: self.absolute,
+            }
+        } else {
+            let mut path = self.path.clone();
+            path.pop();
+            path.extend(other.path.iter().cloned());
+            PathQueryFragment {
+                path: path,
+                query: other.query.clone(),
+                fragment: other.fragment.clone(),
+                absolute: self.absolute,
+            }
+        };
+        joined.normalize();
+        joined
+    }
+
+    /// Append a single key/value pair to the query string, re-serializing it
+    /// as `application/x-www-form-urlencoded`.
+    pub fn add_query_pair(&mut self, key: &str, value: &str) -> &mut PathQueryFragment {
+        let mut pairs = self.query_pairs().unwrap_or_else(Vec::new);
+        pairs.push((key.to_owned(), value.to_owned()));
+        self.query = Some(url::form_urlencoded::serialize(pairs));
+        self
+    }
+
+    /// Replace the query string wholesale with the given key/value pairs,
+    /// re-serialized as `application/x-www-form-urlencoded`.
+    pub fn set_query_pairs(&mut self, pairs: &[(String, String)]) -> &mut PathQueryFragment {
+        self.query = Some(url::form_urlencoded::serialize(pairs.to_vec()));
+        self
+    }
+
+    /// Remove `.` and `..` segments in place, per RFC 3986 §5.2.4
+    /// (`remove_dot_segments`). A `.` segment is dropped. In an absolute
+    /// path a `..` segment pops the previously retained segment, or is
+    /// simply dropped if none remains (it cannot climb above the root); in
+    /// a relative path every `..` is itself retained. All other segments,
+    /// including a trailing empty segment (which preserves a trailing
+    /// slash), are kept in order. If a `.`, or in an absolute path a `..`,
+    /// is the last segment consumed, a trailing empty segment is pushed so
+    /// the result keeps the trailing slash the RFC algorithm always leaves
+    /// behind (e.g. `/a/b/..` normalizes to `/a/`, not `/a`).
+    pub fn normalize(&mut self) -> &mut PathQueryFragment {
+        let len = self.path.len();
+        let mut out: Vec<String> = Vec::with_capacity(len);
+        for (i, segment) in self.path.drain(..).enumerate() {
+            let is_last = i + 1 == len;
+            if segment == "." {
+                if is_last {
+                    out.push(String::new());
+                }
+            } else if segment == ".." {
+                if self.absolute {
+                    out.pop();
+                    if is_last {
+                        out.push(String::new());
+                    }
+                } else {
+                    out.push(segment);
+                }
+            } else {
+                out.push(segment);
+            }
+        }
+        self.path = out;
+        self
+    }
+
+    /// Parse a `path[?query][#fragment]` string in wire (percent-encoded)
+    /// form, e.g. `/a%2Fb/c?x=y#z`. Segments are split on literal `/` bytes
+    /// only, so an encoded `%2F` within a segment is not mistaken for a
+    /// separator.
+    pub fn from_encoded(encoded: &str) -> PathQueryFragment {
+        let (path_and_query, fragment) = match encoded.find('#') {
+            Some(idx) => (&encoded[..idx], Some(encoded[idx + 1..].to_owned())),
+            None => (encoded, None),
+        };
+        let (path_part, query) = match path_and_query.find('?') {
+            Some(idx) => (&path_and_query[..idx], Some(path_and_query[idx + 1..].to_owned())),
+            None => (path_and_query, None),
+        };
+        let absolute = path_part.starts_with('/');
+        let trimmed = if absolute { &path_part[1..] } else { path_part };
+        let path = trimmed.split('/').map(|s| s.to_owned()).collect();
+        PathQueryFragment { path: path, query: query, fragment: fragment, absolute: absolute }
+    }
+
+    /// Re-serialize this path, query and fragment back into wire form, e.g.
+    /// `/a%2Fb/c?x=y#z`. Segments are already kept in wire form (see
+    /// `push_segment()` and `from_encoded()`), so they are joined as-is.
+    pub fn to_encoded_string(&self) -> String {
+        let mut out = String::new();
+        if self.absolute {
+            out.push('/');
+        }
+        for (i, segment) in self.path.iter().enumerate() {
+            if i > 0 {
+                out.push('/');
+            }
+            out.push_str(segment);
+        }
+        if let Some(ref query) = self.query {
+            out.push('?');
+            out.push_str(query);
+        }
+        if let Some(ref fragment) = self.fragment {
+            out.push('#');
+            out.push_str(fragment);
+        }
+        out
+    }
+
+    /// Percent-decode each path segment and interpret it as UTF-8, for
+    /// route matching against logical (decoded) path values. A segment
+    /// with invalid UTF-8 after decoding has the offending bytes replaced
+    /// with U+FFFD.
+    pub fn decoded_segments(&self) -> Vec<Cow<str>> {
+        self.path.iter().map(|segment| {
+            if !segment.contains('%') {
+                return Cow::Borrowed(segment.as_str());
+            }
+            match String::from_utf8(percent_decode_bytes(segment)) {
+                Ok(decoded) => Cow::Owned(decoded),
+                Err(e) => Cow::Owned(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+            }
+        }).collect()
+    }
+}
+
+/// Percent-encode the general delimiters that would otherwise be ambiguous
+/// with a wire-form path's own syntax, plus a literal `%` itself (otherwise
+/// a raw `%` inserted via `push_segment()` would later be misread by
+/// `decoded_segments()` as the start of an escape sequence).
+fn percent_encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for ch in segment.chars() {
+        match ch {
+            '%' | '?' | '#' | '/' | '[' | ']' | '@' => {
+                out.push('%');
+                out.push_str(&format!("{:02X}", ch as u32));
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Percent-decode a string into raw bytes, leaving any `%` not followed by
+/// two hex digits untouched.
+fn percent_decode_bytes(segment: &str) -> Vec<u8> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push((hi << 4) | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'...b'9' => Some(byte - b'0'),
+        b'a'...b'f' => Some(byte - b'a' + 10),
+        b'A'...b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn abs_path(segments: &[&str]) -> PathQueryFragment {
+        PathQueryFragment {
+            path: segments.iter().map(|s| s.to_string()).collect(),
+            query: None,
+            fragment: None,
+            absolute: true,
+        }
+    }
+
+    #[test]
+    fn normalize_removes_dot_segments() {
+        let mut pqf = abs_path(&["foo", "bar", "..", "baz"]);
+        pqf.normalize();
+        assert_eq!(pqf.path, vec!["foo".to_string(), "baz".to_string()]);
+    }
+
+    #[test]
+    fn normalize_drops_dot_segments() {
+        let mut pqf = abs_path(&["foo", ".", "bar"]);
+        pqf.normalize();
+        assert_eq!(pqf.path, vec!["foo".to_string(), "bar".to_string()]);
+    }
+
+    #[test]
+    fn normalize_cannot_pop_above_root() {
+        let mut pqf = abs_path(&["..", "foo"]);
+        pqf.normalize();
+        assert_eq!(pqf.path, vec!["foo".to_string()]);
+    }
+
+    #[test]
+    fn normalize_preserves_leading_dot_dot_on_relative_path() {
+        let mut pqf = abs_path(&["..", "foo"]);
+        pqf.absolute = false;
+        pqf.normalize();
+        assert_eq!(pqf.path, vec!["..".to_string(), "foo".to_string()]);
+    }
+
+    #[test]
+    fn normalize_leaves_trailing_slash_after_popping_last_segment() {
+        let pqf = PathQueryFragment::from_encoded("/a/b/..");
+        let mut pqf = pqf;
+        pqf.normalize();
+        assert_eq!(pqf.to_encoded_string(), "/a/");
+    }
+
+    #[test]
+    fn normalize_leaves_trailing_slash_after_dropping_trailing_dot() {
+        let mut pqf = PathQueryFragment::from_encoded("/a/b/.");
+        pqf.normalize();
+        assert_eq!(pqf.to_encoded_string(), "/a/b/");
+    }
+
+    #[test]
+    fn normalize_preserves_trailing_slash() {
+        let mut pqf = abs_path(&["a", "b", ""]);
+        pqf.normalize();
+        assert_eq!(pqf.path, vec!["a".to_string(), "b".to_string(), "".to_string()]);
+    }
+
+    #[test]
+    fn push_segment_appends() {
+        let mut pqf = abs_path(&["a"]);
+        pqf.push_segment("b");
+        assert_eq!(pqf.path, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn join_merges_relative_reference() {
+        let base = abs_path(&["a", "b"]);
+        let reference = PathQueryFragment {
+            path: vec!["c".to_string()],
+            query: None,
+            fragment: None,
+            absolute: false,
+        };
+        let joined = base.join(&reference);
+        assert_eq!(joined.path, vec!["a".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    fn join_with_absolute_reference_replaces_base() {
+        let base = abs_path(&["a", "b"]);
+        let reference = abs_path(&["x", "y"]);
+        let joined = base.join(&reference);
+        assert_eq!(joined.path, vec!["x".to_string(), "y".to_string()]);
+    }
+
+    #[test]
+    fn join_with_empty_path_reference_keeps_base_path() {
+        let base = abs_path(&["a", "b"]);
+        let reference = PathQueryFragment {
+            path: vec![],
+            query: Some("x=y".to_string()),
+            fragment: None,
+            absolute: false,
+        };
+        let joined = base.join(&reference);
+        assert_eq!(joined.path, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(joined.query, Some("x=y".to_string()));
+    }
+
+    #[test]
+    fn authority_parses_host_and_port() {
+        let authority = Authority::parse("www.example.com:80").unwrap();
+        assert_eq!(authority.userinfo(), None);
+        assert_eq!(authority.host(), "www.example.com");
+        assert_eq!(authority.port(), Some(80));
+    }
+
+    #[test]
+    fn authority_parses_userinfo() {
+        let authority = Authority::parse("user:pass@example.com").unwrap();
+        assert_eq!(authority.userinfo(), Some("user:pass"));
+        assert_eq!(authority.host(), "example.com");
+        assert_eq!(authority.port(), None);
+    }
+
+    #[test]
+    fn authority_parses_bracketed_ipv6_with_port() {
+        let authority = Authority::parse("[2001:db8::1]:8080").unwrap();
+        assert_eq!(authority.host(), "[2001:db8::1]");
+        assert_eq!(authority.port(), Some(8080));
+        assert_eq!(authority.to_string(), "[2001:db8::1]:8080");
+    }
+
+    #[test]
+    fn authority_rejects_malformed_ipv6() {
+        assert!(Authority::parse("[not-an-address]:80").is_err());
+    }
+
+    #[test]
+    fn authority_rejects_out_of_range_port() {
+        assert!(Authority::parse("example.com:99999").is_err());
+    }
+
+    #[test]
+    fn authority_rejects_non_numeric_port() {
+        assert!(Authority::parse("example.com:http").is_err());
+    }
+
+    #[test]
+    fn authority_rejects_unbracketed_ambiguous_colons() {
+        assert!(Authority::parse("2001:db8::1").is_err());
+    }
+
+    #[test]
+    fn request_uri_round_trips_absolute_path() {
+        let text = "/where?q=now";
+        let uri: RequestUri = text.parse().unwrap();
+        assert_eq!(uri.to_string(), text);
+    }
+
+    #[test]
+    fn request_uri_round_trips_absolute_uri() {
+        let text = "http://www.example.org/pub/WWW/TheProject.html";
+        let uri: RequestUri = text.parse().unwrap();
+        match uri {
+            RequestUri::AbsoluteUri(_) => {}
+            _ => panic!("expected AbsoluteUri"),
+        }
+        assert_eq!(uri.to_string(), text);
+    }
+
+    #[test]
+    fn request_uri_round_trips_authority() {
+        let text = "www.example.com:80";
+        let uri: RequestUri = text.parse().unwrap();
+        assert_eq!(uri.to_string(), text);
+    }
+
+    #[test]
+    fn request_uri_round_trips_star() {
+        let uri: RequestUri = "*".parse().unwrap();
+        assert_eq!(uri.to_string(), "*");
+    }
+
+    #[test]
+    fn from_encoded_keeps_percent_2f_distinct_from_separator() {
+        let pqf = PathQueryFragment::from_encoded("/a%2Fb/c");
+        assert_eq!(pqf.path, vec!["a%2Fb".to_string(), "c".to_string()]);
+        assert_eq!(pqf.decoded_segments(), vec![Cow::Borrowed("a/b"), Cow::Borrowed("c")]);
+    }
+
+    #[test]
+    fn from_encoded_to_encoded_string_round_trips() {
+        let text = "/a%2Fb/c?x=y#z";
+        let pqf = PathQueryFragment::from_encoded(text);
+        assert_eq!(pqf.to_encoded_string(), text);
+    }
+
+    #[test]
+    fn push_segment_escapes_reserved_and_percent() {
+        let mut pqf = abs_path(&[]);
+        pqf.push_segment("50%off");
+        assert_eq!(pqf.path, vec!["50%25off".to_string()]);
+        assert_eq!(pqf.decoded_segments(), vec![Cow::Borrowed("50%off")]);
+    }
+
+    #[test]
+    fn push_segment_escapes_literal_slash() {
+        let mut pqf = abs_path(&[]);
+        pqf.push_segment("a/b");
+        assert_eq!(pqf.path, vec!["a%2Fb".to_string()]);
+        assert_eq!(pqf.to_encoded_string(), "/a%2Fb");
+    }
+
+    #[test]
+    fn decoded_segments_reports_invalid_utf8_with_replacement() {
+        let pqf = PathQueryFragment::from_encoded("/%ff%fe");
+        let decoded = pqf.decoded_segments();
+        assert_eq!(decoded[0], Cow::Borrowed("\u{FFFD}\u{FFFD}"));
+    }
+
+    #[test]
+    fn classify_recognizes_origin_form() {
+        assert_eq!(RequestUri::classify("/where?q=now"), TargetForm::Origin);
+    }
+
+    #[test]
+    fn classify_recognizes_absolute_form_with_scheme_and_authority() {
+        assert_eq!(
+            RequestUri::classify("http://www.example.org/pub/WWW/TheProject.html"),
+            TargetForm::Absolute
+        );
+    }
+
+    #[test]
+    fn classify_recognizes_bare_scheme_absolute_form() {
+        assert_eq!(RequestUri::classify("mailto:foo@bar.com"), TargetForm::Absolute);
+    }
+
+    #[test]
+    fn classify_recognizes_authority_form() {
+        assert_eq!(RequestUri::classify("www.example.com:80"), TargetForm::Authority);
+    }
+
+    #[test]
+    fn classify_recognizes_asterisk_form() {
+        assert_eq!(RequestUri::classify("*"), TargetForm::Asterisk);
+    }
+
+    #[test]
+    fn classify_does_not_mistake_dotted_host_for_scheme() {
+        assert_eq!(RequestUri::classify("example.com:abc"), TargetForm::Authority);
+        assert_eq!(RequestUri::classify("a.b.c:notaport"), TargetForm::Authority);
+    }
+
+    #[test]
+    fn dotted_host_with_garbage_port_is_rejected_as_authority() {
+        let err = "example.com:abc".parse::<RequestUri>().unwrap_err();
+        assert_eq!(err, UriError::InvalidPort);
+    }
+
+    #[test]
+    fn classify_agrees_with_from_str_on_bare_scheme() {
+        let form = RequestUri::classify("mailto:foo@bar.com");
+        let parsed: RequestUri = "mailto:foo@bar.com".parse().unwrap();
+        assert_eq!(form, TargetForm::Absolute);
+        match parsed {
+            RequestUri::AbsoluteUri(_) => {}
+            _ => panic!("classify and from_str disagree on bare scheme: form"),
+        }
+    }
 }